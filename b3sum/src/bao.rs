@@ -0,0 +1,295 @@
+//! Bao-style verified streaming: `--encode`, `--decode`/`--verify`, and
+//! `--slice`.
+//!
+//! This builds a self-verifying Merkle tree over a file's 1024-byte chunks,
+//! mirroring BLAKE3's own internal chunk/parent chaining-value construction
+//! (via the `blake3::guts` module, which exists precisely to let tools like
+//! this one reproduce that tree) so the combined encoding's shape matches
+//! the file's natural hash tree and its root is the same 32 bytes as an
+//! ordinary `blake3::hash` of the whole input. The encoding interleaves each
+//! parent node (the 64-byte pair of its two children's chaining values) with
+//! the chunk data it covers, in pre-order, so a decoder can check a
+//! subtree's hash against its parent's before it ever emits that subtree's
+//! bytes, and abort on the first mismatch instead of streaming out
+//! unauthenticated data. A verifier who starts from nothing but the trusted
+//! 32-byte root can authenticate an arbitrary byte range by walking only the
+//! parent hashes on the path to the chunks covering that range (`--slice`),
+//! without touching the rest of the tree.
+
+use anyhow::{ensure, Result};
+use blake3::guts::ChunkState;
+use std::convert::TryInto;
+
+const CHUNK_LEN: usize = 1024;
+const HEADER_LEN: usize = 8; // little-endian u64 total content length
+const SLICE_HEADER_LEN: usize = 24; // total length, slice start, slice length
+
+// Every node's chaining value is a plain `blake3::Hash`; only the single
+// node (a lone chunk, or the outermost parent) that covers the entire
+// original input is finalized with `is_root = true`, matching BLAKE3's own
+// rule for when the ROOT flag applies.
+fn chunk_cv(chunk: &[u8], chunk_counter: u64, is_root: bool) -> blake3::Hash {
+    ChunkState::new(chunk_counter).update(chunk).finalize(is_root)
+}
+
+fn parent_cv(left: &blake3::Hash, right: &blake3::Hash, is_root: bool) -> blake3::Hash {
+    blake3::guts::parent_cv(left, right, is_root)
+}
+
+pub(crate) fn hash_from_slice(bytes: &[u8]) -> blake3::Hash {
+    let array: [u8; blake3::OUT_LEN] = bytes.try_into().expect("32-byte hash slice");
+    array.into()
+}
+
+// BLAKE3 splits a subtree of more than one chunk at the largest power-of-two
+// number of chunks that's strictly less than the total, so the right
+// subtree is never empty (though its last chunk may be partial).
+fn left_subtree_len(total_len: usize) -> usize {
+    let total_chunks = (total_len + CHUNK_LEN - 1) / CHUNK_LEN;
+    debug_assert!(total_chunks >= 2);
+    let mut left_chunks = 1;
+    while left_chunks * 2 < total_chunks {
+        left_chunks *= 2;
+    }
+    left_chunks * CHUNK_LEN
+}
+
+fn encode_subtree(data: &[u8], chunk_counter: u64, is_root: bool, out: &mut Vec<u8>) -> blake3::Hash {
+    if data.len() <= CHUNK_LEN {
+        out.extend_from_slice(data);
+        return chunk_cv(data, chunk_counter, is_root);
+    }
+    let split = left_subtree_len(data.len());
+    let (left, right) = data.split_at(split);
+    // The parent node must precede the data it covers, but we can't fill it
+    // in until we know both children's hashes; reserve the space up front.
+    let parent_pos = out.len();
+    out.extend_from_slice(&[0; 64]);
+    let left_hash = encode_subtree(left, chunk_counter, false, out);
+    let right_counter = chunk_counter + (split / CHUNK_LEN) as u64;
+    let right_hash = encode_subtree(right, right_counter, false, out);
+    out[parent_pos..parent_pos + 32].copy_from_slice(left_hash.as_bytes());
+    out[parent_pos + 32..parent_pos + 64].copy_from_slice(right_hash.as_bytes());
+    parent_cv(&left_hash, &right_hash, is_root)
+}
+
+/// Produce a combined encoding of `data` and return it alongside the root
+/// hash a verifier should remember in order to check it later. This root is
+/// always exactly `blake3::hash(data)`.
+pub(crate) fn encode(data: &[u8]) -> (Vec<u8>, blake3::Hash) {
+    let mut out = Vec::with_capacity(HEADER_LEN + data.len() + data.len() / CHUNK_LEN * 64 + 64);
+    out.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    let root = encode_subtree(data, 0, true, &mut out);
+    (out, root)
+}
+
+fn decode_subtree(
+    encoded: &mut &[u8],
+    len: usize,
+    chunk_counter: u64,
+    is_root: bool,
+    expected: &blake3::Hash,
+    out: &mut Vec<u8>,
+) -> Result<()> {
+    if len <= CHUNK_LEN {
+        ensure!(encoded.len() >= len, "truncated encoding: missing chunk data");
+        let chunk = &encoded[..len];
+        ensure!(
+            &chunk_cv(chunk, chunk_counter, is_root) == expected,
+            "chunk hash mismatch: corrupted encoding"
+        );
+        out.extend_from_slice(chunk);
+        *encoded = &encoded[len..];
+        return Ok(());
+    }
+    ensure!(encoded.len() >= 64, "truncated encoding: missing parent node");
+    let left_hash = hash_from_slice(&encoded[..32]);
+    let right_hash = hash_from_slice(&encoded[32..64]);
+    ensure!(
+        &parent_cv(&left_hash, &right_hash, is_root) == expected,
+        "parent hash mismatch: corrupted encoding"
+    );
+    *encoded = &encoded[64..];
+    let split = left_subtree_len(len);
+    let right_counter = chunk_counter + (split / CHUNK_LEN) as u64;
+    decode_subtree(encoded, split, chunk_counter, false, &left_hash, out)?;
+    decode_subtree(encoded, len - split, right_counter, false, &right_hash, out)
+}
+
+/// Stream a combined encoding back out, validating every subtree against its
+/// parent (and ultimately against `expected_root`) before trusting its
+/// bytes. Returns the decoded content, or the first error encountered —
+/// including on the first byte flipped by corruption.
+pub(crate) fn decode(mut encoded: &[u8], expected_root: &blake3::Hash) -> Result<Vec<u8>> {
+    ensure!(encoded.len() >= HEADER_LEN, "truncated encoding: missing header");
+    let len = u64::from_le_bytes(encoded[..HEADER_LEN].try_into().unwrap()) as usize;
+    encoded = &encoded[HEADER_LEN..];
+    let mut out = Vec::with_capacity(len);
+    decode_subtree(&mut encoded, len, 0, true, expected_root, &mut out)?;
+    Ok(out)
+}
+
+fn subtree_hash(data: &[u8], chunk_counter: u64) -> blake3::Hash {
+    encode_subtree(data, chunk_counter, false, &mut Vec::new())
+}
+
+fn encode_subtree_slice(
+    data: &[u8],
+    base_offset: u64,
+    chunk_counter: u64,
+    start: u64,
+    end: u64,
+    is_root: bool,
+    out: &mut Vec<u8>,
+) -> blake3::Hash {
+    let subtree_end = base_offset + data.len() as u64;
+    if data.len() <= CHUNK_LEN {
+        let hash = chunk_cv(data, chunk_counter, is_root);
+        if base_offset < end && subtree_end > start {
+            out.extend_from_slice(data);
+        }
+        return hash;
+    }
+    let split = left_subtree_len(data.len());
+    let (left, right) = data.split_at(split);
+    let mid = base_offset + split as u64;
+    let right_counter = chunk_counter + (split / CHUNK_LEN) as u64;
+    let left_overlaps = base_offset < end && mid > start;
+    let right_overlaps = mid < end && subtree_end > start;
+
+    let parent_pos = out.len();
+    out.extend_from_slice(&[0; 64]);
+    let left_hash = if left_overlaps {
+        encode_subtree_slice(left, base_offset, chunk_counter, start, end, false, out)
+    } else {
+        subtree_hash(left, chunk_counter)
+    };
+    let right_hash = if right_overlaps {
+        encode_subtree_slice(right, mid, right_counter, start, end, false, out)
+    } else {
+        subtree_hash(right, right_counter)
+    };
+    out[parent_pos..parent_pos + 32].copy_from_slice(left_hash.as_bytes());
+    out[parent_pos + 32..parent_pos + 64].copy_from_slice(right_hash.as_bytes());
+    parent_cv(&left_hash, &right_hash, is_root)
+}
+
+/// Extract a self-verifying sub-encoding covering just `[start, start+len)`
+/// of `data`. The slice still carries every parent hash on the path from the
+/// root to the covered chunks (so a verifier can check it against the same
+/// root the whole-file encoding would have), but omits the bytes and interior
+/// parent nodes of subtrees that fall entirely outside the range; only their
+/// own hashes remain, inside their parent's node.
+pub(crate) fn encode_slice(data: &[u8], start: u64, len: u64) -> (Vec<u8>, blake3::Hash) {
+    let end = start.saturating_add(len).min(data.len() as u64);
+    let mut out = Vec::new();
+    out.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    out.extend_from_slice(&start.to_le_bytes());
+    out.extend_from_slice(&len.to_le_bytes());
+    let root = encode_subtree_slice(data, 0, 0, start, end, true, &mut out);
+    (out, root)
+}
+
+fn decode_subtree_slice(
+    encoded: &mut &[u8],
+    base_offset: u64,
+    data_len: usize,
+    chunk_counter: u64,
+    start: u64,
+    end: u64,
+    is_root: bool,
+    expected: &blake3::Hash,
+    out: &mut Vec<u8>,
+) -> Result<()> {
+    let subtree_end = base_offset + data_len as u64;
+    let overlaps = base_offset < end && subtree_end > start;
+    if !overlaps {
+        return Ok(());
+    }
+    if data_len <= CHUNK_LEN {
+        ensure!(encoded.len() >= data_len, "truncated slice: missing chunk data");
+        let chunk = &encoded[..data_len];
+        ensure!(
+            &chunk_cv(chunk, chunk_counter, is_root) == expected,
+            "chunk hash mismatch: corrupted slice"
+        );
+        out.extend_from_slice(chunk);
+        *encoded = &encoded[data_len..];
+        return Ok(());
+    }
+    ensure!(encoded.len() >= 64, "truncated slice: missing parent node");
+    let left_hash = hash_from_slice(&encoded[..32]);
+    let right_hash = hash_from_slice(&encoded[32..64]);
+    ensure!(
+        &parent_cv(&left_hash, &right_hash, is_root) == expected,
+        "parent hash mismatch: corrupted slice"
+    );
+    *encoded = &encoded[64..];
+    let split = left_subtree_len(data_len);
+    let mid = base_offset + split as u64;
+    let right_counter = chunk_counter + (split / CHUNK_LEN) as u64;
+    decode_subtree_slice(
+        encoded,
+        base_offset,
+        split,
+        chunk_counter,
+        start,
+        end,
+        false,
+        &left_hash,
+        out,
+    )?;
+    decode_subtree_slice(
+        encoded,
+        mid,
+        data_len - split,
+        right_counter,
+        start,
+        end,
+        false,
+        &right_hash,
+        out,
+    )
+}
+
+/// Decode a slice produced by [`encode_slice`], checking it against the same
+/// trusted root the full file would hash to.
+pub(crate) fn decode_slice(mut encoded: &[u8], expected_root: &blake3::Hash) -> Result<Vec<u8>> {
+    ensure!(
+        encoded.len() >= SLICE_HEADER_LEN,
+        "truncated slice: missing header"
+    );
+    let total_len = u64::from_le_bytes(encoded[0..8].try_into().unwrap());
+    let start = u64::from_le_bytes(encoded[8..16].try_into().unwrap());
+    let len = u64::from_le_bytes(encoded[16..24].try_into().unwrap());
+    encoded = &encoded[SLICE_HEADER_LEN..];
+    let end = start.saturating_add(len).min(total_len);
+    // A `start` past the end of the file can't overlap anything; clamp it
+    // down to `end` so the final range computation below can't underflow.
+    let start = start.min(end);
+
+    let mut out = Vec::new();
+    if total_len > 0 {
+        decode_subtree_slice(
+            &mut encoded,
+            0,
+            total_len as usize,
+            0,
+            start,
+            end,
+            true,
+            expected_root,
+            &mut out,
+        )?;
+    } else {
+        ensure!(&chunk_cv(&[], 0, true) == expected_root, "root hash mismatch");
+    }
+
+    // Chunk-granularity reads may have pulled in a little extra data at the
+    // edges; clip down to exactly the requested range.
+    let floor = (start / CHUNK_LEN as u64) * CHUNK_LEN as u64;
+    let skip = (start - floor) as usize;
+    let keep = (end - start) as usize;
+    ensure!(out.len() >= skip + keep, "slice decoded shorter than requested");
+    Ok(out[skip..skip + keep].to_vec())
+}
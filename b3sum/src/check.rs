@@ -0,0 +1,218 @@
+//! Implementation of `b3sum --check`, which reads checksum files in the same
+//! `<hex>  <name>` format this binary emits (including the optional leading
+//! backslash that signals escaping) and re-hashes each referenced file to
+//! confirm it matches.
+
+use crate::Args;
+use anyhow::{Context, Result};
+use std::ffi::{OsStr, OsString};
+use std::fs::File;
+use std::io::{self, BufRead, Read};
+use std::path::{Path, PathBuf};
+
+// On Unix a name is built back up from its exact original bytes, so a
+// checksum line parsed from a --zero checkfile can reference a filename that
+// isn't valid UTF-8. Elsewhere we fall back to a lossy string, matching
+// `name_bytes` in main.rs.
+#[cfg(unix)]
+fn os_string_from_bytes(bytes: Vec<u8>) -> OsString {
+    use std::os::unix::ffi::OsStringExt;
+    OsString::from_vec(bytes)
+}
+
+#[cfg(not(unix))]
+fn os_string_from_bytes(bytes: Vec<u8>) -> OsString {
+    String::from_utf8_lossy(&bytes).into_owned().into()
+}
+
+fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn rfind_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.is_empty() || haystack.len() < needle.len() {
+        return None;
+    }
+    (0..=haystack.len() - needle.len()).rev().find(|&i| &haystack[i..i + needle.len()] == needle)
+}
+
+fn unescape_filename(escaped: &[u8]) -> Vec<u8> {
+    let mut unescaped = Vec::with_capacity(escaped.len());
+    let mut bytes = escaped.iter().copied();
+    while let Some(b) = bytes.next() {
+        if b != b'\\' {
+            unescaped.push(b);
+            continue;
+        }
+        match bytes.next() {
+            Some(b'n') => unescaped.push(b'\n'),
+            Some(b'\\') => unescaped.push(b'\\'),
+            Some(other) => {
+                unescaped.push(b'\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push(b'\\'),
+        }
+    }
+    unescaped
+}
+
+/// A single parsed `<hex>  <name>` checksum line.
+struct CheckLine {
+    expected_hex: String,
+    name: OsString,
+}
+
+// BSD-style "BLAKE3 (name) = hex" lines, as emitted by `--tag`. The tagged
+// format has no escaping convention, so this assumes the name doesn't
+// contain the literal separator ") = ".
+fn parse_tagged_check_line(line: &[u8]) -> Option<CheckLine> {
+    let rest = line.strip_prefix(b"BLAKE3 (")?;
+    let sep_index = rfind_subslice(rest, b") = ")?;
+    let name = &rest[..sep_index];
+    let hex_part = &rest[sep_index + 4..];
+    if hex_part.is_empty() || name.is_empty() || !hex_part.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+    Some(CheckLine {
+        expected_hex: String::from_utf8(hex_part.to_vec()).expect("ascii hex digits are valid utf8"),
+        name: os_string_from_bytes(name.to_vec()),
+    })
+}
+
+fn parse_check_line(line: &[u8], zero: bool) -> Option<CheckLine> {
+    if let Some(check_line) = parse_tagged_check_line(line) {
+        return Some(check_line);
+    }
+
+    // With --zero there's no escaping convention, so a leading backslash is
+    // just a literal character, not an escape marker.
+    let (line, is_escaped) = if zero {
+        (line, false)
+    } else {
+        match line.strip_prefix(b"\\") {
+            Some(rest) => (rest, true),
+            None => (line, false),
+        }
+    };
+    let sep_index = find_subslice(line, b"  ")?;
+    let hex_part = &line[..sep_index];
+    if hex_part.is_empty() || !hex_part.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+    let name_part = &line[sep_index + 2..];
+    if name_part.is_empty() {
+        return None;
+    }
+    let name_bytes = if is_escaped {
+        unescape_filename(name_part)
+    } else {
+        name_part.to_vec()
+    };
+    Some(CheckLine {
+        expected_hex: String::from_utf8(hex_part.to_vec()).expect("ascii hex digits are valid utf8"),
+        name: os_string_from_bytes(name_bytes),
+    })
+}
+
+// Raw line bytes, one per checksum line. Under --zero these are the exact
+// NUL-delimited record bytes (so a name round-trips losslessly even if it
+// isn't valid UTF-8, mirroring `name_bytes` on the emitting side); otherwise
+// they're UTF-8 lines, since the non-zero escaping convention can only
+// represent a name that was itself valid UTF-8 to begin with.
+fn checkfile_lines(checkfile_arg: &OsStr, zero: bool) -> Result<Vec<Vec<u8>>> {
+    let mut reader: Box<dyn BufRead> = if checkfile_arg == crate::FILE_ARGS_STDIN {
+        Box::new(io::BufReader::new(io::stdin()))
+    } else {
+        let file = File::open(checkfile_arg)
+            .with_context(|| format!("failed to open {:?}", checkfile_arg))?;
+        Box::new(io::BufReader::new(file))
+    };
+    if !zero {
+        return reader
+            .lines()
+            .map(|line| line.map(String::into_bytes))
+            .collect::<io::Result<Vec<Vec<u8>>>>()
+            .map_err(Into::into);
+    }
+    let mut contents = Vec::new();
+    reader.read_to_end(&mut contents)?;
+    Ok(contents
+        .split(|&b| b == 0)
+        .filter(|record| !record.is_empty())
+        .map(|record| record.to_vec())
+        .collect())
+}
+
+pub(crate) fn check_files(args: &Args) -> Result<()> {
+    let base_hasher = crate::make_base_hasher(args)?;
+
+    let mut any_failure = false;
+    let mut any_malformed = false;
+
+    for checkfile_arg in args.file_args() {
+        let lines = checkfile_lines(&checkfile_arg, args.zero)?;
+        for (line_number, line) in lines.iter().enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+            let Some(CheckLine { expected_hex, name }) = parse_check_line(line, args.zero) else {
+                any_malformed = true;
+                if args.warn {
+                    eprintln!(
+                        "b3sum: {:?}:{}: improperly formatted BLAKE3 checksum line",
+                        checkfile_arg,
+                        line_number + 1,
+                    );
+                }
+                continue;
+            };
+
+            let path = PathBuf::from(&name);
+            let display_name = name.to_string_lossy();
+            match check_one(&base_hasher, args, &path, &expected_hex) {
+                Ok(true) => {
+                    if !args.status && !args.quiet {
+                        println!("{}: OK", display_name);
+                    }
+                }
+                Ok(false) => {
+                    any_failure = true;
+                    if !args.status {
+                        println!("{}: FAILED", display_name);
+                    }
+                }
+                Err(e) => {
+                    if args.ignore_missing && !path_exists(&path) {
+                        continue;
+                    }
+                    any_failure = true;
+                    if !args.status {
+                        println!("{}: FAILED open or read", display_name);
+                        eprintln!("b3sum: {:?}: {}", path, e);
+                    }
+                }
+            }
+        }
+    }
+
+    if any_failure || any_malformed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn path_exists(path: &Path) -> bool {
+    path.symlink_metadata().is_ok()
+}
+
+fn check_one(
+    base_hasher: &blake3::Hasher,
+    args: &Args,
+    path: &Path,
+    expected_hex: &str,
+) -> Result<bool> {
+    let hasher = crate::hash_one(base_hasher, args, path)?;
+    let actual_hex = crate::output_hex_string(hasher, args);
+    Ok(actual_hex.eq_ignore_ascii_case(expected_hex))
+}
@@ -0,0 +1,479 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+mod bao;
+mod check;
+mod tree;
+
+const KEYED_HASH_KEY_LEN: usize = blake3::KEY_LEN;
+const FILE_ARGS_STDIN: &str = "-";
+
+/// A command line implementation of the BLAKE3 hash function.
+#[derive(Parser)]
+#[command(name = "b3sum", version)]
+struct Args {
+    /// Files to hash, or checksum files to verify with --check
+    ///
+    /// When no file is given, or when "-" is given, read standard input.
+    file: Vec<OsString>,
+
+    /// The number of output bytes, prior to hex encoding
+    #[arg(long, value_name = "LEN")]
+    length: Option<u64>,
+
+    /// Use the keyed hashing mode, with the 32-byte key read from stdin
+    #[arg(long)]
+    keyed: bool,
+
+    /// Use the key derivation mode, with the given context string
+    ///
+    /// Cannot be used with --keyed.
+    #[arg(long, value_name = "CONTEXT", conflicts_with = "keyed")]
+    derive_key: Option<String>,
+
+    /// Disable memory mapping
+    #[arg(long)]
+    no_mmap: bool,
+
+    /// Omit filenames in the output
+    #[arg(long)]
+    no_names: bool,
+
+    /// Write raw output bytes to stdout, rather than hex
+    ///
+    /// --raw only supports a single input.
+    #[arg(long)]
+    raw: bool,
+
+    /// Read BLAKE3 checksums from the FILEs and check them
+    #[arg(short = 'c', long)]
+    check: bool,
+
+    /// Don't print "OK" for each successfully verified file
+    ///
+    /// Only has an effect with --check.
+    #[arg(long)]
+    quiet: bool,
+
+    /// Don't output anything, indicate results via the exit code only
+    ///
+    /// Only has an effect with --check.
+    #[arg(long)]
+    status: bool,
+
+    /// Warn about improperly formatted checksum lines
+    ///
+    /// Only has an effect with --check.
+    #[arg(long)]
+    warn: bool,
+
+    /// Don't fail or report status for missing files
+    ///
+    /// Only has an effect with --check.
+    #[arg(long)]
+    ignore_missing: bool,
+
+    /// Terminate each output line with NUL, not newline, and disable escaping
+    ///
+    /// With --check, split input on NUL instead of newline; the "OK"/"FAILED"
+    /// lines --check prints still end in a newline. This allows filenames
+    /// that contain newlines or backslashes to round-trip safely.
+    #[arg(short = 'z', long)]
+    zero: bool,
+
+    /// Walk directory arguments recursively, hashing every regular file found
+    #[arg(short = 'r', long)]
+    recursive: bool,
+
+    /// With --recursive, follow symlinks instead of skipping them
+    #[arg(long)]
+    follow_symlinks: bool,
+
+    /// With --recursive, fold all per-file hashes into one tree digest
+    ///
+    /// The digest depends on every file's path and contents, but not on the
+    /// order in which the directory was walked. The digest is always 32
+    /// bytes; --length does not apply to it.
+    #[arg(long)]
+    tree: bool,
+
+    /// Print a BSD-style "BLAKE3 (filename) = hash" line instead of the
+    /// default "hash  filename"
+    ///
+    /// Incompatible with --raw. Since the tagged format has no escaping
+    /// convention, it's also rejected for names that would otherwise need
+    /// escaping, unless --zero is given too. --check auto-detects this
+    /// format alongside the default one.
+    #[arg(long)]
+    tag: bool,
+
+    /// Produce a bao-style combined encoding of FILE, writing the root hash
+    /// to stderr and the encoding itself to stdout
+    #[arg(long, value_name = "FILE")]
+    encode: Option<PathBuf>,
+
+    /// Decode a combined encoding from stdin, requires --root, writing the
+    /// verified content to stdout
+    #[arg(long)]
+    decode: bool,
+
+    /// Like --decode, but only check the encoding from stdin; don't print
+    /// the decoded content anywhere
+    #[arg(long)]
+    verify: bool,
+
+    /// The trusted root hash to check --decode/--verify against
+    #[arg(long, value_name = "HEX")]
+    root: Option<String>,
+
+    /// With --encode, extract a self-verifying sub-encoding covering just
+    /// this byte range, instead of the whole file
+    #[arg(long, num_args = 2, value_names = ["START", "LEN"])]
+    slice: Option<Vec<u64>>,
+
+    /// With --decode/--verify, treat stdin as a --slice encoding rather than
+    /// a full combined encoding
+    #[arg(long)]
+    slice_encoding: bool,
+}
+
+impl Args {
+    fn file_args(&self) -> Vec<OsString> {
+        if self.file.is_empty() {
+            vec![FILE_ARGS_STDIN.into()]
+        } else {
+            self.file.clone()
+        }
+    }
+
+    fn num_outbytes(&self) -> u64 {
+        self.length.unwrap_or(blake3::OUT_LEN as u64)
+    }
+}
+
+fn read_key_from_stdin() -> Result<[u8; KEYED_HASH_KEY_LEN]> {
+    let mut bytes = [0; KEYED_HASH_KEY_LEN];
+    io::stdin()
+        .read_exact(&mut bytes)
+        .context("failed to read key from stdin")?;
+    Ok(bytes)
+}
+
+fn make_base_hasher(args: &Args) -> Result<blake3::Hasher> {
+    if args.keyed {
+        Ok(blake3::Hasher::new_keyed(&read_key_from_stdin()?))
+    } else if let Some(context) = &args.derive_key {
+        Ok(blake3::Hasher::new_derive_key(context))
+    } else {
+        Ok(blake3::Hasher::new())
+    }
+}
+
+// Memory mapping is purely a performance optimization. Return None where
+// mapping isn't possible (e.g. the file is empty, or it's something like a
+// pipe), so the caller falls back to regular reads. If the file is too large
+// to map, or mapping is attempted and fails, return the error instead.
+fn maybe_memmap_file(file: &File) -> Result<Option<memmap2::Mmap>> {
+    let metadata = file.metadata()?;
+    let file_size = metadata.len();
+    Ok(if !metadata.is_file() {
+        None
+    } else if file_size == 0 {
+        None
+    } else if file_size > isize::MAX as u64 {
+        bail!("too large to safely map");
+    } else {
+        let map = unsafe {
+            memmap2::MmapOptions::new()
+                .len(file_size as usize)
+                .map(file)
+                .context("memmap failed")?
+        };
+        Some(map)
+    })
+}
+
+/// Hash a single file argument (or stdin, for `-`) against a clone of
+/// `base_hasher`, returning the filled-but-not-finalized hasher.
+fn hash_one(base_hasher: &blake3::Hasher, args: &Args, path: &Path) -> Result<blake3::Hasher> {
+    let mut hasher = base_hasher.clone();
+    if path == Path::new(FILE_ARGS_STDIN) {
+        let stdin = io::stdin();
+        let mut stdin_lock = stdin.lock();
+        io::copy(&mut stdin_lock, &mut hasher).context("failed to read stdin")?;
+        return Ok(hasher);
+    }
+    let mut file = File::open(path).with_context(|| format!("failed to open {:?}", path))?;
+    if !args.no_mmap {
+        if let Some(map) = maybe_memmap_file(&file)? {
+            hasher.update_rayon(&map);
+            return Ok(hasher);
+        }
+    }
+    io::copy(&mut file, &mut hasher).with_context(|| format!("failed to read {:?}", path))?;
+    Ok(hasher)
+}
+
+/// Render the hash of a filled hasher as a hex string, honoring `--length`.
+fn output_hex_string(hasher: blake3::Hasher, args: &Args) -> String {
+    let mut output_reader = hasher.finalize_xof();
+    let mut bytes = vec![0; args.num_outbytes() as usize];
+    output_reader.fill(&mut bytes);
+    hex::encode(bytes)
+}
+
+// On Windows we print forward slashes instead of backslashes, so that
+// output is portable across platforms (see test_slash_normalization_on_windows).
+fn filepath_to_string(filepath: &Path) -> String {
+    let unicode_cow = filepath.to_string_lossy();
+    let mut filepath_string = unicode_cow.to_string();
+    if cfg!(windows) {
+        filepath_string = filepath_string.replace('\\', "/");
+    }
+    filepath_string
+}
+
+/// Escape backslashes and embedded newlines, returning the escaped string and
+/// whether any escaping was necessary (in which case the caller must print a
+/// leading backslash on the whole output line).
+fn escape_filename(name: &str) -> (String, bool) {
+    if name.contains('\\') || name.contains('\n') {
+        let escaped = name.replace('\\', "\\\\").replace('\n', "\\n");
+        (escaped, true)
+    } else {
+        (name.to_string(), false)
+    }
+}
+
+// Raw, unescaped bytes of a path's name, used for --zero output. On Unix this
+// is the exact OsStr bytes, so round-tripping is lossless even for invalid
+// UTF-8; elsewhere we fall back to a lossy string.
+#[cfg(unix)]
+fn name_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn name_bytes(path: &Path) -> Vec<u8> {
+    filepath_to_string(path).into_bytes()
+}
+
+fn print_result_line(hex: &str, path: &Path, args: &Args) -> Result<()> {
+    let mut stdout = io::stdout();
+    if args.tag {
+        let name = filepath_to_string(path);
+        let (escaped, needs_escape) = escape_filename(&name);
+        if needs_escape && !args.zero {
+            bail!(
+                "{:?}: --tag has no escaping convention for names containing \
+                 a backslash or a newline; pass --zero as well",
+                path
+            );
+        }
+        if args.no_names && args.zero {
+            stdout.write_all(hex.as_bytes())?;
+            stdout.write_all(b"\0")?;
+        } else if args.no_names {
+            println!("{}", hex);
+        } else if args.zero {
+            stdout.write_all(b"BLAKE3 (")?;
+            stdout.write_all(&name_bytes(path))?;
+            stdout.write_all(format!(") = {}", hex).as_bytes())?;
+            stdout.write_all(b"\0")?;
+        } else {
+            println!("BLAKE3 ({}) = {}", escaped, hex);
+        }
+        return Ok(());
+    }
+    if args.zero {
+        stdout.write_all(hex.as_bytes())?;
+        if !args.no_names {
+            stdout.write_all(b"  ")?;
+            stdout.write_all(&name_bytes(path))?;
+        }
+        stdout.write_all(b"\0")?;
+        return Ok(());
+    }
+    if args.no_names {
+        println!("{}", hex);
+        return Ok(());
+    }
+    let (escaped, is_escaped) = escape_filename(&filepath_to_string(path));
+    if is_escaped {
+        print!("\\");
+    }
+    println!("{}  {}", hex, escaped);
+    Ok(())
+}
+
+/// Hash a single path and print its result line (or raw bytes). Returns
+/// `false`, after printing an error to stderr, if hashing or writing failed.
+fn hash_and_print(base_hasher: &blake3::Hasher, args: &Args, path: &Path) -> bool {
+    hash_and_print_as(base_hasher, args, path, path)
+}
+
+/// Like `hash_and_print`, but reads `path` while printing `display_path` as
+/// the name, for `--recursive`, where the name shown is relative to the
+/// walked directory but the path opened on disk is not.
+fn hash_and_print_as(base_hasher: &blake3::Hasher, args: &Args, path: &Path, display_path: &Path) -> bool {
+    let report_err = |e: anyhow::Error| eprintln!("b3sum: {:?}: {}", path, e);
+    match hash_one(base_hasher, args, path) {
+        Ok(hasher) => {
+            if args.raw {
+                let mut bytes = vec![0; args.num_outbytes() as usize];
+                hasher.finalize_xof().fill(&mut bytes);
+                if let Err(e) = io::stdout().write_all(&bytes) {
+                    report_err(e.into());
+                    return false;
+                }
+            } else {
+                let hex = output_hex_string(hasher, args);
+                if let Err(e) = print_result_line(&hex, display_path, args) {
+                    report_err(e);
+                    return false;
+                }
+            }
+            true
+        }
+        Err(e) => {
+            report_err(e);
+            false
+        }
+    }
+}
+
+fn parse_root_arg(args: &Args) -> Result<blake3::Hash> {
+    let hex_str = args
+        .root
+        .as_deref()
+        .context("--root <HEX> is required for --decode/--verify")?;
+    let bytes = hex::decode(hex_str).context("--root is not valid hex")?;
+    anyhow::ensure!(
+        bytes.len() == blake3::OUT_LEN,
+        "--root must be a {}-byte hash ({} hex characters)",
+        blake3::OUT_LEN,
+        2 * blake3::OUT_LEN,
+    );
+    Ok(bao::hash_from_slice(&bytes))
+}
+
+fn run_encode(args: &Args, path: &Path) -> Result<()> {
+    let data = std::fs::read(path).with_context(|| format!("failed to read {:?}", path))?;
+    let (encoded, root) = match args.slice.as_deref() {
+        Some([start, len]) => bao::encode_slice(&data, *start, *len),
+        Some(_) => bail!("--slice takes exactly two values: START LEN"),
+        None => bao::encode(&data),
+    };
+    eprintln!("{}", root.to_hex());
+    io::stdout().write_all(&encoded)?;
+    Ok(())
+}
+
+fn run_decode(args: &Args) -> Result<()> {
+    let root = parse_root_arg(args)?;
+    let mut encoded = Vec::new();
+    io::stdin()
+        .read_to_end(&mut encoded)
+        .context("failed to read encoding from stdin")?;
+    let decoded = if args.slice_encoding {
+        bao::decode_slice(&encoded, &root)?
+    } else {
+        bao::decode(&encoded, &root)?
+    };
+    if !args.verify {
+        io::stdout().write_all(&decoded)?;
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    if let Some(path) = args.encode.clone() {
+        return run_encode(&args, &path);
+    }
+    if args.decode || args.verify {
+        return run_decode(&args);
+    }
+
+    if args.check {
+        return check::check_files(&args);
+    }
+
+    if args.tree && !args.recursive {
+        bail!("b3sum: --tree requires --recursive");
+    }
+
+    if args.raw && args.tag {
+        bail!("b3sum: the --raw flag and --tag cannot be used together");
+    }
+
+    let file_args = args.file_args();
+    if args.raw && file_args.len() > 1 {
+        bail!("b3sum: the --raw flag can only be used with a single input");
+    }
+    if args.raw && args.recursive && !args.tree {
+        bail!(
+            "b3sum: the --raw flag can only be used with a single input; \
+             pass --tree to reduce a --recursive directory to one digest"
+        );
+    }
+
+    let base_hasher = make_base_hasher(&args)?;
+
+    let mut did_error = false;
+    for file_arg in file_args {
+        let path = PathBuf::from(&file_arg);
+
+        let is_recursive_dir =
+            args.recursive && path != Path::new(FILE_ARGS_STDIN) && path.is_dir();
+        if !is_recursive_dir {
+            if !hash_and_print(&base_hasher, &args, &path) {
+                did_error = true;
+            }
+            continue;
+        }
+
+        let entries = match tree::walk(&path, args.follow_symlinks) {
+            Ok(entries) => entries,
+            Err(e) => {
+                did_error = true;
+                eprintln!("b3sum: {:?}: {}", path, e);
+                continue;
+            }
+        };
+
+        if !args.tree {
+            for entry in &entries {
+                if !hash_and_print_as(&base_hasher, &args, &entry.path, Path::new(&entry.rel_path)) {
+                    did_error = true;
+                }
+            }
+            continue;
+        }
+
+        match tree::tree_hash(&entries, &base_hasher, &args) {
+            Ok(hash) => {
+                if args.raw {
+                    io::stdout().write_all(hash.as_bytes())?;
+                } else {
+                    print_result_line(&hash.to_hex().to_string(), &path, &args)?;
+                }
+            }
+            Err(e) => {
+                did_error = true;
+                eprintln!("b3sum: {:?}: {}", path, e);
+            }
+        }
+    }
+    if did_error {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,104 @@
+//! Directory traversal for `--recursive` and `--tree`.
+
+use crate::Args;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Domain separation for the per-entry hash folded into a `--tree` digest, so
+/// that a tree-entry hash can never collide with an ordinary file hash or
+/// with another entry's constituent bytes.
+const TREE_ENTRY_CONTEXT: &str = "BLAKE3 b3sum 2024-06-01 17:00:00 tree entry context";
+
+/// A regular file discovered while walking a directory tree.
+pub(crate) struct TreeEntry {
+    /// The real path to open when hashing this entry's contents.
+    pub(crate) path: PathBuf,
+    /// The forward-slash-normalized path relative to the walked root
+    /// directory, used for sorting, `--recursive`'s printed name, and the
+    /// `--tree` entry hash, so none of those depend on where the tree
+    /// happens to live on disk.
+    pub(crate) rel_path: String,
+}
+
+/// Walk `root` and return every regular file beneath it, sorted by their
+/// path relative to `root` so the result doesn't depend on the host
+/// platform, on where `root` lives on disk, or on the OS's directory
+/// traversal order.
+pub(crate) fn walk(root: &Path, follow_symlinks: bool) -> Result<Vec<TreeEntry>> {
+    let mut entries = Vec::new();
+    walk_into(root, root, follow_symlinks, &mut entries)?;
+    entries.sort_by(|a, b| a.rel_path.cmp(&b.rel_path));
+    Ok(entries)
+}
+
+fn push_if_file(root: &Path, path: &Path, metadata: &fs::Metadata, entries: &mut Vec<TreeEntry>) {
+    if metadata.is_file() {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        entries.push(TreeEntry {
+            rel_path: crate::filepath_to_string(relative),
+            path: path.to_path_buf(),
+        });
+    }
+    // Anything else (sockets, FIFOs, ...) is silently skipped.
+}
+
+fn walk_into(root: &Path, path: &Path, follow_symlinks: bool, entries: &mut Vec<TreeEntry>) -> Result<()> {
+    let metadata =
+        fs::symlink_metadata(path).with_context(|| format!("failed to stat {:?}", path))?;
+
+    if metadata.is_symlink() {
+        if !follow_symlinks {
+            return Ok(());
+        }
+        let resolved =
+            fs::metadata(path).with_context(|| format!("failed to stat {:?}", path))?;
+        if resolved.is_dir() {
+            return walk_dir(root, path, follow_symlinks, entries);
+        }
+        push_if_file(root, path, &resolved, entries);
+        return Ok(());
+    }
+
+    if metadata.is_dir() {
+        return walk_dir(root, path, follow_symlinks, entries);
+    }
+
+    push_if_file(root, path, &metadata, entries);
+    Ok(())
+}
+
+fn walk_dir(root: &Path, dir: &Path, follow_symlinks: bool, entries: &mut Vec<TreeEntry>) -> Result<()> {
+    for child in
+        fs::read_dir(dir).with_context(|| format!("failed to read directory {:?}", dir))?
+    {
+        let child = child.with_context(|| format!("failed to read directory {:?}", dir))?;
+        walk_into(root, &child.path(), follow_symlinks, entries)?;
+    }
+    Ok(())
+}
+
+/// Fold every entry's file hash into a single digest, domain-separating each
+/// entry by its length-prefixed path relative to the walked root before
+/// mixing it into the root hasher. Because `entries` is already sorted by
+/// `walk`, the result depends only on file contents and tree shape relative
+/// to the root — not on directory traversal order, or on where the tree
+/// happens to live on disk.
+pub(crate) fn tree_hash(
+    entries: &[TreeEntry],
+    base_hasher: &blake3::Hasher,
+    args: &Args,
+) -> Result<blake3::Hash> {
+    let mut root_hasher = blake3::Hasher::new();
+    for entry in entries {
+        let file_hasher = crate::hash_one(base_hasher, args, &entry.path)?;
+        let file_hash = file_hasher.finalize();
+
+        let mut entry_hasher = blake3::Hasher::new_derive_key(TREE_ENTRY_CONTEXT);
+        entry_hasher.update(&(entry.rel_path.len() as u64).to_le_bytes());
+        entry_hasher.update(entry.rel_path.as_bytes());
+        entry_hasher.update(file_hash.as_bytes());
+        root_hasher.update(entry_hasher.finalize().as_bytes());
+    }
+    Ok(root_hasher.finalize())
+}
@@ -180,6 +180,300 @@ fn test_newline_and_backslash_escaping_on_unix() {
     assert_eq!(expected, output);
 }
 
+fn run_encode(path: &std::path::Path) -> (Vec<u8>, String) {
+    let output = cmd!(b3sum_exe(), "--encode", path)
+        .stdout_capture()
+        .stderr_capture()
+        .run()
+        .unwrap();
+    let root = String::from_utf8(output.stderr).unwrap().trim().to_string();
+    (output.stdout, root)
+}
+
+#[test]
+fn test_encode_decode_round_trip() {
+    let f = tempfile::NamedTempFile::new().unwrap();
+    let contents = vec![7u8; 10 * 1024 + 1]; // multiple chunks, uneven split
+    f.as_file().write_all(&contents).unwrap();
+    f.as_file().flush().unwrap();
+
+    let (encoding, root) = run_encode(f.path());
+    assert_eq!(root, blake3::hash(&contents).to_hex().to_string());
+
+    let decoded = cmd!(b3sum_exe(), "--decode", "--root", &root)
+        .stdin_bytes(encoding)
+        .stdout_capture()
+        .run()
+        .unwrap()
+        .stdout;
+    assert_eq!(contents, decoded);
+}
+
+#[test]
+fn test_decode_detects_corruption() {
+    let f = tempfile::NamedTempFile::new().unwrap();
+    let contents = vec![9u8; 5000];
+    f.as_file().write_all(&contents).unwrap();
+    f.as_file().flush().unwrap();
+
+    let (mut encoding, root) = run_encode(f.path());
+    // Flip the last byte of the encoded chunk data.
+    let last = encoding.len() - 1;
+    encoding[last] ^= 0xff;
+
+    let result = cmd!(b3sum_exe(), "--decode", "--root", &root)
+        .stdin_bytes(encoding)
+        .stdout_capture()
+        .stderr_capture()
+        .run();
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_verify_does_not_print_content() {
+    let f = tempfile::NamedTempFile::new().unwrap();
+    f.as_file().write_all(b"hello world").unwrap();
+    f.as_file().flush().unwrap();
+
+    let (encoding, root) = run_encode(f.path());
+    let output = cmd!(b3sum_exe(), "--verify", "--root", &root)
+        .stdin_bytes(encoding)
+        .stdout_capture()
+        .run()
+        .unwrap();
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_slice_round_trip() {
+    let f = tempfile::NamedTempFile::new().unwrap();
+    let contents: Vec<u8> = (0..20_000u32).map(|i| i as u8).collect();
+    f.as_file().write_all(&contents).unwrap();
+    f.as_file().flush().unwrap();
+
+    let output = cmd!(b3sum_exe(), "--encode", f.path(), "--slice", "1024", "2048")
+        .stdout_capture()
+        .stderr_capture()
+        .run()
+        .unwrap();
+    let root = String::from_utf8(output.stderr).unwrap().trim().to_string();
+    assert_eq!(root, blake3::hash(&contents).to_hex().to_string());
+
+    let decoded = cmd!(
+        b3sum_exe(),
+        "--decode",
+        "--slice-encoding",
+        "--root",
+        &root
+    )
+    .stdin_bytes(output.stdout)
+    .stdout_capture()
+    .run()
+    .unwrap()
+    .stdout;
+    assert_eq!(&contents[1024..1024 + 2048], decoded.as_slice());
+}
+
+#[test]
+fn test_tag_emits_bsd_style_output() {
+    let f = tempfile::NamedTempFile::new().unwrap();
+    f.as_file().write_all(b"foo").unwrap();
+    f.as_file().flush().unwrap();
+
+    let output = cmd!(b3sum_exe(), "--tag", f.path()).read().unwrap();
+    let expected = format!(
+        "BLAKE3 ({}) = {}",
+        f.path().to_string_lossy().replace('\\', "/"),
+        blake3::hash(b"foo").to_hex(),
+    );
+    assert_eq!(expected, output);
+}
+
+#[test]
+fn test_tag_round_trips_through_check() {
+    let f = tempfile::NamedTempFile::new().unwrap();
+    f.as_file().write_all(b"foo").unwrap();
+    f.as_file().flush().unwrap();
+
+    let checksums = cmd!(b3sum_exe(), "--tag", f.path()).read().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let checkfile = dir.path().join("checksums");
+    fs::write(&checkfile, checksums).unwrap();
+
+    let output = cmd!(b3sum_exe(), "--check", &checkfile)
+        .stdout_capture()
+        .run()
+        .unwrap();
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_tag_with_raw_is_an_error() {
+    let f = tempfile::NamedTempFile::new().unwrap();
+    let result = cmd!(b3sum_exe(), "--tag", "--raw", f.path())
+        .stdout_capture()
+        .stderr_capture()
+        .run();
+    assert!(result.is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_tag_rejects_escapable_names_without_zero() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("abc\ndef");
+    fs::write(&path, b"").unwrap();
+
+    let result = cmd!(b3sum_exe(), "--tag", &path)
+        .stderr_capture()
+        .run();
+    assert!(result.is_err());
+
+    // With --zero the raw name is allowed through untouched.
+    let output = cmd!(b3sum_exe(), "--tag", "--zero", &path)
+        .stdout_capture()
+        .run();
+    assert!(output.is_ok());
+}
+
+#[test]
+fn test_recursive_hashes_every_file_sorted() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("sub")).unwrap();
+    fs::write(dir.path().join("sub").join("b"), b"bar").unwrap();
+    fs::write(dir.path().join("a"), b"foo").unwrap();
+
+    let output = cmd!(b3sum_exe(), "--recursive", dir.path())
+        .read()
+        .unwrap();
+    let expected = format!(
+        "{}  a\n{}  sub/b",
+        blake3::hash(b"foo").to_hex(),
+        blake3::hash(b"bar").to_hex(),
+    );
+    assert_eq!(expected, output);
+}
+
+#[test]
+fn test_tree_digest_is_order_independent() {
+    let dir1 = tempfile::tempdir().unwrap();
+    fs::create_dir(dir1.path().join("sub")).unwrap();
+    fs::write(dir1.path().join("sub").join("b"), b"bar").unwrap();
+    fs::write(dir1.path().join("a"), b"foo").unwrap();
+
+    let dir2 = tempfile::tempdir().unwrap();
+    fs::write(dir2.path().join("a"), b"foo").unwrap();
+    fs::create_dir(dir2.path().join("sub")).unwrap();
+    fs::write(dir2.path().join("sub").join("b"), b"bar").unwrap();
+
+    let output1 = cmd!(b3sum_exe(), "--recursive", "--tree", "--no-names", dir1.path())
+        .read()
+        .unwrap();
+    let output2 = cmd!(b3sum_exe(), "--recursive", "--tree", "--no-names", dir2.path())
+        .read()
+        .unwrap();
+    assert_eq!(output1, output2);
+
+    // Changing a file's contents changes the tree digest.
+    fs::write(dir2.path().join("a"), b"different").unwrap();
+    let output3 = cmd!(b3sum_exe(), "--recursive", "--tree", "--no-names", dir2.path())
+        .read()
+        .unwrap();
+    assert_ne!(output1, output3);
+}
+
+#[test]
+fn test_tree_without_recursive_is_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let result = cmd!(b3sum_exe(), "--tree", dir.path())
+        .stderr_capture()
+        .run();
+    assert!(result.is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_zero_disables_escaping_on_unix() {
+    let empty_hash = blake3::hash(b"").to_hex();
+    let dir = tempfile::tempdir().unwrap();
+    let names = ["abcdef", "abc\ndef", "abc\\def"];
+    for name in &names {
+        fs::write(dir.path().join(name), b"").unwrap();
+    }
+    let mut args = vec!["--zero"];
+    args.extend_from_slice(&names);
+    let output = cmd(b3sum_exe(), &args)
+        .dir(dir.path())
+        .stdout_capture()
+        .run()
+        .unwrap()
+        .stdout;
+    let records: Vec<&[u8]> = output.split(|&b| b == 0).filter(|r| !r.is_empty()).collect();
+    assert!(records.iter().all(|r| !r.starts_with(b"\\")));
+    assert_eq!(records.len(), names.len());
+    for (record, name) in records.iter().zip(names.iter()) {
+        let expected = format!("{}  {}", empty_hash, name);
+        assert_eq!(expected.as_bytes(), *record);
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn test_check_with_zero_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let names = ["abcdef", "abc\ndef", "abc\\def"];
+    for name in &names {
+        fs::write(dir.path().join(name), b"").unwrap();
+    }
+    let mut args = vec!["--zero"];
+    args.extend_from_slice(&names);
+    let checksums = cmd(b3sum_exe(), &args)
+        .dir(dir.path())
+        .stdout_capture()
+        .run()
+        .unwrap()
+        .stdout;
+    let checkfile = dir.path().join("checksums");
+    fs::write(&checkfile, &checksums).unwrap();
+
+    let output = cmd!(b3sum_exe(), "--check", "--zero", &checkfile)
+        .dir(dir.path())
+        .stdout_capture()
+        .run()
+        .unwrap();
+    assert!(output.status.success());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_check_with_zero_round_trips_invalid_unicode() {
+    use std::os::unix::ffi::OsStringExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let name = OsString::from_vec(b"abc\xffdef".to_vec());
+    let path = dir.path().join(&name);
+    let write_result = fs::write(&path, b"");
+    if cfg!(target_os = "linux") {
+        write_result.expect("Linux should allow invalid Unicode");
+    } else if write_result.is_err() {
+        return;
+    }
+
+    let checksums = cmd!(b3sum_exe(), "--zero", &path)
+        .stdout_capture()
+        .run()
+        .unwrap()
+        .stdout;
+    let checkfile = dir.path().join("checksums");
+    fs::write(&checkfile, &checksums).unwrap();
+
+    let output = cmd!(b3sum_exe(), "--check", "--zero", &checkfile)
+        .stdout_capture()
+        .run()
+        .unwrap();
+    assert!(output.status.success());
+}
+
 #[test]
 #[cfg(windows)]
 fn test_slash_normalization_on_windows() {
@@ -258,6 +552,87 @@ fn test_invalid_unicode_on_unix() {
     assert_eq!(expected, output);
 }
 
+#[test]
+fn test_check_ok() {
+    let dir = tempfile::tempdir().unwrap();
+    let file1 = dir.path().join("file1");
+    fs::write(&file1, b"foo").unwrap();
+    let file2 = dir.path().join("file2");
+    fs::write(&file2, b"bar").unwrap();
+
+    let checksums = cmd!(b3sum_exe(), &file1, &file2).read().unwrap();
+    let checkfile = dir.path().join("checksums");
+    fs::write(&checkfile, checksums).unwrap();
+
+    let output = cmd!(b3sum_exe(), "--check", &checkfile)
+        .stdout_capture()
+        .run()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(&format!("{}: OK", file1.to_string_lossy().replace('\\', "/"))));
+    assert!(stdout.contains(&format!("{}: OK", file2.to_string_lossy().replace('\\', "/"))));
+}
+
+#[test]
+fn test_check_failed() {
+    let dir = tempfile::tempdir().unwrap();
+    let file1 = dir.path().join("file1");
+    fs::write(&file1, b"foo").unwrap();
+
+    let checksums = cmd!(b3sum_exe(), &file1).read().unwrap();
+    fs::write(&file1, b"not foo anymore").unwrap();
+    let checkfile = dir.path().join("checksums");
+    fs::write(&checkfile, checksums).unwrap();
+
+    let result = cmd!(b3sum_exe(), "--check", &checkfile)
+        .stdout_capture()
+        .run();
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_check_missing_is_an_error_unless_ignored() {
+    let dir = tempfile::tempdir().unwrap();
+    let file1 = dir.path().join("file1");
+    fs::write(&file1, b"foo").unwrap();
+    let missing = dir.path().join("does-not-exist");
+
+    let mut checksums = cmd!(b3sum_exe(), &file1).read().unwrap();
+    checksums.push('\n');
+    checksums.push_str(&format!(
+        "{}  {}",
+        blake3::hash(b"foo").to_hex(),
+        missing.to_string_lossy().replace('\\', "/"),
+    ));
+    let checkfile = dir.path().join("checksums");
+    fs::write(&checkfile, checksums).unwrap();
+
+    let result = cmd!(b3sum_exe(), "--check", &checkfile)
+        .stdout_capture()
+        .stderr_capture()
+        .run();
+    assert!(result.is_err());
+
+    let output = cmd!(b3sum_exe(), "--check", "--ignore-missing", &checkfile)
+        .stdout_capture()
+        .run()
+        .unwrap();
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_check_malformed_line_is_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let checkfile = dir.path().join("checksums");
+    fs::write(&checkfile, "this is not a checksum line\n").unwrap();
+
+    let result = cmd!(b3sum_exe(), "--check", "--warn", &checkfile)
+        .stderr_capture()
+        .run();
+    assert!(result.is_err());
+}
+
 #[test]
 #[cfg(windows)]
 fn test_invalid_unicode_on_windows() {